@@ -1,4 +1,4 @@
-use crate::crypto::{read_decrypted, save_encrypted, CryptoError};
+use crate::crypto::{read_decrypted, save_encrypted, CryptoError, EncryptionType};
 use rand::prelude::*;
 use std::collections::hash_set::HashSet;
 use std::fs::File;
@@ -10,11 +10,77 @@ pub enum ActionType {
     Ignore,
 }
 
+#[derive(bincode::Encode, bincode::Decode, Clone, Copy)]
+pub struct MetaPolicy {
+    pub mode: bool,
+    pub owner: bool,
+    pub size: bool,
+    pub mtime: bool,
+    pub ctime: bool,
+    pub inode: bool,
+    pub nlink: bool,
+}
+
+impl MetaPolicy {
+    pub fn all() -> Self {
+        Self {
+            mode: true,
+            owner: true,
+            size: true,
+            mtime: true,
+            ctime: true,
+            inode: true,
+            nlink: true,
+        }
+    }
+
+    fn parse_mask(tokens: &str) -> Option<Self> {
+        let mut p = Self {
+            mode: false,
+            owner: false,
+            size: false,
+            mtime: false,
+            ctime: false,
+            inode: false,
+            nlink: false,
+        };
+
+        for tok in tokens.split(',') {
+            match tok.trim() {
+                "all" => return Some(Self::all()),
+                "mode" => p.mode = true,
+                "owner" => p.owner = true,
+                "size" => p.size = true,
+                "mtime" => p.mtime = true,
+                "ctime" => p.ctime = true,
+                "inode" => p.inode = true,
+                "nlink" => p.nlink = true,
+                _ => return None,
+            }
+        }
+
+        Some(p)
+    }
+}
+
+impl Default for MetaPolicy {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+#[derive(bincode::Encode, bincode::Decode)]
+pub struct ScanEntry {
+    pub path: String,
+    pub policy: MetaPolicy,
+}
+
 #[derive(bincode::Encode, bincode::Decode)]
 pub struct Config {
     pub secret: [u8; 192],
-    pub scans: Vec<String>,
+    pub scans: Vec<ScanEntry>,
     pub ignores: HashSet<PathBuf>,
+    pub check_xattrs: bool,
 }
 
 impl Config {
@@ -23,6 +89,7 @@ impl Config {
             secret: [0u8; 192],
             scans: Vec::new(),
             ignores: HashSet::new(),
+            check_xattrs: false,
         };
 
         c.gen_new_secret();
@@ -37,7 +104,12 @@ impl Config {
     }
 }
 
-pub fn gen_config(infile: &str, outfile: &str, passphrase: &str) -> Result<(), CryptoError> {
+pub fn gen_config(
+    infile: &str,
+    outfile: &str,
+    passphrase: &str,
+    enc: EncryptionType,
+) -> Result<(), CryptoError> {
     let mut fd = BufReader::new(File::open(infile)?);
     let mut config = Config::new();
 
@@ -57,9 +129,35 @@ pub fn gen_config(infile: &str, outfile: &str, passphrase: &str) -> Result<(), C
             "[IGNORE]\n" | "[ignore]\n" => {
                 current_type = ActionType::Ignore;
             }
+            "[XATTRS]\n" | "[xattrs]\n" => {
+                config.check_xattrs = true;
+            }
             _ => match &current_type {
                 ActionType::Scan => {
-                    config.scans.push(String::from(line.trim_end_matches('\n')));
+                    let entry = line.trim_end_matches('\n');
+                    let scan = match entry.rsplit_once(char::is_whitespace) {
+                        Some((path, mask)) => match MetaPolicy::parse_mask(mask) {
+                            Some(policy) if !path.trim().is_empty() => ScanEntry {
+                                path: String::from(path.trim_end()),
+                                policy,
+                            },
+                            _ => {
+                                eprintln!(
+                                    "Warning: could not parse attribute mask on scan line {:?}, using the whole line as path with the default policy",
+                                    entry
+                                );
+                                ScanEntry {
+                                    path: String::from(entry),
+                                    policy: MetaPolicy::all(),
+                                }
+                            }
+                        },
+                        None => ScanEntry {
+                            path: String::from(entry),
+                            policy: MetaPolicy::all(),
+                        },
+                    };
+                    config.scans.push(scan);
                 }
                 ActionType::Ignore => {
                     config
@@ -71,7 +169,7 @@ pub fn gen_config(infile: &str, outfile: &str, passphrase: &str) -> Result<(), C
         line.clear();
     }
 
-    save_encrypted(config, outfile, passphrase)?;
+    save_encrypted(config, outfile, passphrase, enc)?;
 
     Ok(())
 }
@@ -1,12 +1,131 @@
 use std::fs::File;
 use std::io::{Read, Write};
-use std::path::PathBuf;
 
+use argon2::{Algorithm as Argon2Algorithm, Argon2, Params, Version};
+use rand::prelude::*;
 use ring::aead::{
     Aad, Algorithm, BoundKey, Nonce, NonceSequence, OpeningKey, SealingKey, UnboundKey,
-    CHACHA20_POLY1305,
+    AES_256_GCM, CHACHA20_POLY1305,
 };
 
+const MAGIC: &[u8; 4] = b"TWRS";
+const FORMAT_VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+const HEADER_LEN: usize = 4 + 1 + 1 + 4 + 4 + 4 + SALT_LEN;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum EncryptionType {
+    #[default]
+    ChaCha20Poly1305,
+    AesGcm256,
+}
+
+impl EncryptionType {
+    fn algorithm(&self) -> &'static Algorithm {
+        match self {
+            Self::ChaCha20Poly1305 => &CHACHA20_POLY1305,
+            Self::AesGcm256 => &AES_256_GCM,
+        }
+    }
+
+    fn to_byte(self) -> u8 {
+        match self {
+            Self::ChaCha20Poly1305 => 0,
+            Self::AesGcm256 => 1,
+        }
+    }
+
+    fn from_byte(b: u8) -> Result<Self, CryptoError> {
+        match b {
+            0 => Ok(Self::ChaCha20Poly1305),
+            1 => Ok(Self::AesGcm256),
+            _ => Err(CryptoError::UnsupportedFormat),
+        }
+    }
+}
+
+const DEFAULT_M_COST: u32 = 64 * 1024;
+const DEFAULT_T_COST: u32 = 3;
+const DEFAULT_P_COST: u32 = 1;
+
+struct FileHeader {
+    enc: EncryptionType,
+    m_cost: u32,
+    t_cost: u32,
+    p_cost: u32,
+    salt: [u8; SALT_LEN],
+}
+
+impl FileHeader {
+    fn generate(enc: EncryptionType) -> Self {
+        let mut salt = [0u8; SALT_LEN];
+        rand::thread_rng().fill(&mut salt);
+
+        Self {
+            enc,
+            m_cost: DEFAULT_M_COST,
+            t_cost: DEFAULT_T_COST,
+            p_cost: DEFAULT_P_COST,
+            salt,
+        }
+    }
+
+    fn header_bytes(&self) -> [u8; HEADER_LEN] {
+        let mut buf = [0u8; HEADER_LEN];
+        buf[0..4].copy_from_slice(MAGIC);
+        buf[4] = FORMAT_VERSION;
+        buf[5] = self.enc.to_byte();
+        buf[6..10].copy_from_slice(&self.m_cost.to_be_bytes());
+        buf[10..14].copy_from_slice(&self.t_cost.to_be_bytes());
+        buf[14..18].copy_from_slice(&self.p_cost.to_be_bytes());
+        buf[18..34].copy_from_slice(&self.salt);
+        buf
+    }
+
+    fn parse(data: &[u8]) -> Result<Self, CryptoError> {
+        if data.len() < HEADER_LEN {
+            return Err(CryptoError::BadHeader);
+        }
+
+        if &data[0..4] != MAGIC {
+            return Err(CryptoError::BadHeader);
+        }
+
+        if data[4] != FORMAT_VERSION {
+            return Err(CryptoError::UnsupportedFormat);
+        }
+
+        let enc = EncryptionType::from_byte(data[5])?;
+        let m_cost = u32::from_be_bytes(data[6..10].try_into().unwrap());
+        let t_cost = u32::from_be_bytes(data[10..14].try_into().unwrap());
+        let p_cost = u32::from_be_bytes(data[14..18].try_into().unwrap());
+        let mut salt = [0u8; SALT_LEN];
+        salt.copy_from_slice(&data[18..34]);
+
+        Ok(Self {
+            enc,
+            m_cost,
+            t_cost,
+            p_cost,
+            salt,
+        })
+    }
+
+    fn derive_key(&self, passphrase: &str) -> Result<[u8; KEY_LEN], CryptoError> {
+        let params = Params::new(self.m_cost, self.t_cost, self.p_cost, Some(KEY_LEN))
+            .map_err(|_| CryptoError::CouldNotCreateKey)?;
+        let argon = Argon2::new(Argon2Algorithm::Argon2id, Version::V0x13, params);
+
+        let mut key = [0u8; KEY_LEN];
+        argon
+            .hash_password_into(passphrase.as_bytes(), &self.salt, &mut key)
+            .map_err(|_| CryptoError::CouldNotCreateKey)?;
+
+        Ok(key)
+    }
+}
+
 struct FixedNonceSequence<'a> {
     counter: &'a mut u128,
 }
@@ -37,36 +156,14 @@ impl<'a> NonceSequence for FixedNonceSequence<'a> {
     }
 }
 
-fn get_compatible_passphrase(algo: &Algorithm, passphrase: &str) -> Vec<u8> {
-    let mut s: Vec<u8> = Vec::from(passphrase.as_bytes());
-    let orig_len = s.len();
-
-    if orig_len < algo.key_len() {
-        while s.len() != algo.key_len() {
-            let nl = {
-                if s.len() + orig_len < algo.key_len() {
-                    orig_len
-                } else {
-                    algo.key_len() - s.len()
-                }
-            };
-
-            s.extend_from_within(0..nl);
-        }
-    } else {
-        s.truncate(algo.key_len());
-    }
-
-    s
-}
-
 #[derive(Debug)]
 pub enum CryptoError {
     WrongPassphrase,
     CouldNotCreateKey,
     CouldNotEncrypt,
     EncryptedDataTooShort,
-    CannotGetNonceFromExistingFile,
+    UnsupportedFormat,
+    BadHeader,
     EncodeError(bincode::error::EncodeError),
     DecodeError(bincode::error::DecodeError),
     IoError(std::io::Error),
@@ -90,63 +187,23 @@ impl From<bincode::error::DecodeError> for CryptoError {
     }
 }
 
-fn get_next_nonce_from_file<P: Into<PathBuf>>(
-    path: P,
-    passphrase: &str,
-) -> Result<u128, CryptoError> {
-    let path: PathBuf = path.into();
-
-    if !path.exists() {
-        return Ok(0);
-    }
-
-    let mut fd = File::open(path)?;
-
-    if fd.metadata()?.len() < 16 {
-        return Err(CryptoError::CannotGetNonceFromExistingFile);
-    }
-
-    let mut data = Vec::new();
-    fd.read_to_end(&mut data)?;
-
-    let mut nonce: u128 = {
-        let mut b = [0u8; 16];
-        for i in (0..16).rev() {
-            b[i] = data.pop().unwrap();
-        }
-
-        u128::from_be_bytes(b)
-    };
-
-    let seq = FixedNonceSequence::new(&mut nonce);
-
-    let comp_passphrase = get_compatible_passphrase(&CHACHA20_POLY1305, passphrase);
-    let unbound_key = UnboundKey::new(&CHACHA20_POLY1305, &comp_passphrase)
-        .map_err(|_| CryptoError::CouldNotCreateKey)?;
-    let mut opening_key = OpeningKey::new(unbound_key, seq);
-
-    opening_key
-        .open_in_place(Aad::empty(), &mut data)
-        .map_err(|_| CryptoError::CannotGetNonceFromExistingFile)?;
-
-    Ok(nonce + 1)
-}
-
 pub fn save_encrypted<T: bincode::Encode>(
     obj: T,
     outfile: &str,
     passphrase: &str,
+    enc: EncryptionType,
 ) -> Result<(), CryptoError> {
     let mut data = bincode::encode_to_vec(obj, bincode::config::standard())?;
 
-    let comp_passphrase = get_compatible_passphrase(&CHACHA20_POLY1305, passphrase);
-
-    let unbound_key = UnboundKey::new(&CHACHA20_POLY1305, &comp_passphrase)
-        .map_err(|_| CryptoError::CouldNotCreateKey)?;
+    let header = FileHeader::generate(enc);
+    let key = header.derive_key(passphrase)?;
 
-    let mut nonce: u128 = get_next_nonce_from_file(outfile, passphrase)?;
-    let first_nonce = nonce;
+    let unbound_key =
+        UnboundKey::new(enc.algorithm(), &key).map_err(|_| CryptoError::CouldNotCreateKey)?;
 
+    // Each save mints a fresh salt (and thus key) in `FileHeader::generate`, so
+    // nonce reuse across files isn't a hazard and the nonce can simply start at 0.
+    let mut nonce: u128 = 0;
     let seq = FixedNonceSequence::new(&mut nonce);
 
     let mut sealing_key = SealingKey::new(unbound_key, seq);
@@ -156,8 +213,9 @@ pub fn save_encrypted<T: bincode::Encode>(
         .map_err(|_| CryptoError::CouldNotEncrypt)?;
 
     let mut fd = File::create(outfile)?;
+    fd.write_all(&header.header_bytes())?;
     fd.write_all(&data)?;
-    fd.write(&first_nonce.to_be_bytes())?;
+    fd.write_all(&0u128.to_be_bytes())?;
 
     Ok(())
 }
@@ -170,7 +228,7 @@ pub fn read_decrypted<T: bincode::Decode>(
 
     File::open(infile)?.read_to_end(&mut data)?;
 
-    if data.len() < 16 {
+    if data.len() < HEADER_LEN + 16 {
         return Err(CryptoError::EncryptedDataTooShort);
     }
 
@@ -183,15 +241,16 @@ pub fn read_decrypted<T: bincode::Decode>(
     };
     let seq = FixedNonceSequence::new(&mut nonce);
 
-    let comp_passphrase = get_compatible_passphrase(&CHACHA20_POLY1305, passphrase);
+    let header = FileHeader::parse(&data)?;
+    let key = header.derive_key(passphrase)?;
 
-    let unbound_key = UnboundKey::new(&CHACHA20_POLY1305, &comp_passphrase)
+    let unbound_key = UnboundKey::new(header.enc.algorithm(), &key)
         .map_err(|_| CryptoError::CouldNotCreateKey)?;
     let mut opening_key = OpeningKey::new(unbound_key, seq);
 
     opening_key
-        .open_in_place(Aad::empty(), &mut data)
+        .open_in_place(Aad::empty(), &mut data[HEADER_LEN..])
         .map_err(|_| CryptoError::WrongPassphrase)?;
 
-    Ok(bincode::decode_from_slice(&data, bincode::config::standard())?.0)
+    Ok(bincode::decode_from_slice(&data[HEADER_LEN..], bincode::config::standard())?.0)
 }
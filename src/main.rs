@@ -1,8 +1,10 @@
 mod config;
 mod crypto;
+mod report;
 mod tripwirs;
 
 use config::{gen_config, get_config, Config};
+use crypto::EncryptionType;
 use tripwirs::*;
 
 use std::env;
@@ -14,12 +16,51 @@ use std::io::{self, Write};
 fn print_help(progname: &str) {
     eprintln!("Usage: {} [command] [args...]\n", progname);
     eprintln!(
-        "\tcreate_config [plain config input path] [config output path]
-\tgenerate_db [config input path] [db output path]
-\tcompare_db [config input path] [db]"
+        "\tcreate_config [plain config input path] [config output path] [--cipher chacha20|aes256]
+\tgenerate_db [config input path] [db output path] [--cipher chacha20|aes256] [--jobs n]
+\tcompare_db [config input path] [db] [--jobs n] [--json]"
     );
 }
 
+fn get_encryption_type(args: &[String]) -> EncryptionType {
+    let mut it = args.iter();
+    while let Some(a) = it.next() {
+        if a == "--cipher" {
+            match it.next().map(String::as_str) {
+                Some("chacha20") | Some("chacha20poly1305") => {
+                    return EncryptionType::ChaCha20Poly1305
+                }
+                Some("aes256") | Some("aesgcm256") => return EncryptionType::AesGcm256,
+                other => {
+                    eprintln!("Unknown cipher {:?}, using default", other.unwrap_or(""));
+                }
+            }
+        }
+    }
+
+    EncryptionType::default()
+}
+
+fn get_jobs(args: &[String]) -> usize {
+    let mut it = args.iter();
+    while let Some(a) = it.next() {
+        if a == "--jobs" {
+            match it.next().and_then(|n| n.parse::<usize>().ok()) {
+                Some(n) if n > 0 => return n,
+                _ => eprintln!("Invalid --jobs value, using default"),
+            }
+        }
+    }
+
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+fn get_json(args: &[String]) -> bool {
+    args.iter().any(|a| a == "--json")
+}
+
 #[inline]
 fn get_passphrase() -> String {
     let mut passphrase = String::new();
@@ -59,23 +100,34 @@ fn main() {
 
     match args[1].as_str() {
         "create_config" => {
+            let enc = get_encryption_type(&args);
             blame(
-                gen_config(&args[2], &args[3], &get_passphrase()),
+                gen_config(&args[2], &args[3], &get_passphrase(), enc),
                 "Could not generate config",
             );
         }
         "generate_db" => {
+            let enc = get_encryption_type(&args);
+            let jobs = get_jobs(&args);
             let p = get_passphrase();
             let conf: Config = blame(get_config(&args[2], &p), "Could not get config");
-            blame(gen_db(&conf, &args[3], &p), "Could not create database");
+            blame(
+                gen_db(&conf, &args[3], &p, enc, jobs),
+                "Could not create database",
+            );
         }
         "compare_db" => {
+            let jobs = get_jobs(&args);
+            let json = get_json(&args);
             let p = get_passphrase();
             let conf: Config = blame(get_config(&args[2], &p), "Could not get config");
-            blame(
-                compare_db(&conf, &args[3], &p),
+            let changed = blame(
+                compare_db(&conf, &args[3], &p, jobs, json),
                 "Could not compare database",
             );
+            if changed {
+                exit(1);
+            }
         }
         "show_db" => {
             let p = get_passphrase();
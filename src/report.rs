@@ -0,0 +1,128 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[derive(serde::Serialize)]
+#[serde(tag = "type")]
+pub enum Change {
+    NewFile,
+    NewSymlink,
+    NewDirectory,
+    NewDevice { kind: &'static str },
+    Removed { kind: &'static str },
+    TypeChanged { from: &'static str, to: &'static str },
+    HashChanged { old: String, new: String },
+    DeviceNumbersChanged { old: String, new: String },
+    MetadataChanged { field: &'static str, old: String, new: String },
+    XattrAdded { name: String },
+    XattrRemoved { name: String },
+    XattrChanged { name: String },
+    VerifyFailed { error: String },
+}
+
+#[derive(serde::Serialize)]
+pub struct Entry {
+    pub path: String,
+    #[serde(flatten)]
+    pub change: Change,
+}
+
+#[derive(Default, serde::Serialize)]
+pub struct Summary {
+    pub new: usize,
+    pub removed: usize,
+    pub hash_changed: usize,
+    pub type_changed: usize,
+    pub metadata_changed: usize,
+    pub verify_failed: usize,
+}
+
+impl Summary {
+    fn record(&mut self, change: &Change) {
+        match change {
+            Change::NewFile | Change::NewSymlink | Change::NewDirectory | Change::NewDevice { .. } => {
+                self.new += 1
+            }
+            Change::Removed { .. } => self.removed += 1,
+            Change::HashChanged { .. } => self.hash_changed += 1,
+            Change::TypeChanged { .. } => self.type_changed += 1,
+            Change::MetadataChanged { .. }
+            | Change::DeviceNumbersChanged { .. }
+            | Change::XattrAdded { .. }
+            | Change::XattrRemoved { .. }
+            | Change::XattrChanged { .. } => self.metadata_changed += 1,
+            Change::VerifyFailed { .. } => self.verify_failed += 1,
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+pub struct Report {
+    pub roots: Vec<String>,
+    pub timestamp: u64,
+    pub changes: Vec<Entry>,
+    pub summary: Summary,
+}
+
+impl Report {
+    fn new(roots: Vec<String>, changes: Vec<Entry>) -> Self {
+        let mut summary = Summary::default();
+        for entry in &changes {
+            summary.record(&entry.change);
+        }
+
+        Self {
+            roots,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            changes,
+            summary,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Sink {
+    changed: Arc<AtomicBool>,
+    changes: Option<Arc<Mutex<Vec<Entry>>>>,
+}
+
+impl Sink {
+    pub fn text() -> Self {
+        Self { changed: Arc::new(AtomicBool::new(false)), changes: None }
+    }
+
+    pub fn json() -> Self {
+        Self {
+            changed: Arc::new(AtomicBool::new(false)),
+            changes: Some(Arc::new(Mutex::new(Vec::new()))),
+        }
+    }
+
+    pub fn is_json(&self) -> bool {
+        self.changes.is_some()
+    }
+
+    pub fn record(&self, path: &Path, change: Change) {
+        self.changed.store(true, Ordering::Relaxed);
+        if let Some(changes) = &self.changes {
+            changes.lock().unwrap().push(Entry { path: path.display().to_string(), change });
+        }
+    }
+
+    pub fn finish(self, roots: Vec<String>) -> bool {
+        let changed = self.changed.load(Ordering::Relaxed);
+        if let Some(changes) = self.changes {
+            let changes = Arc::try_unwrap(changes).map(|m| m.into_inner().unwrap()).unwrap_or_default();
+            let report = Report::new(roots, changes);
+            match serde_json::to_string_pretty(&report) {
+                Ok(json) => println!("{json}"),
+                Err(e) => eprintln!("Could not serialize report: {e}"),
+            }
+        }
+        changed
+    }
+}
@@ -2,11 +2,16 @@ use core::hash::Hasher;
 use std::collections::HashMap;
 use std::fs::File;
 use std::io::Read;
-use std::path::PathBuf;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
 use xxhash_rust::xxh3::Xxh3;
 
 use crate::config::*;
-use crate::crypto::{read_decrypted, save_encrypted, CryptoError};
+use crate::crypto::{read_decrypted, save_encrypted, CryptoError, EncryptionType};
+use crate::report::{Change, Sink};
 
 #[inline]
 fn get_filehash(hasher: &mut Xxh3, file: &PathBuf) -> std::io::Result<u64> {
@@ -29,23 +34,350 @@ fn get_filehash(hasher: &mut Xxh3, file: &PathBuf) -> std::io::Result<u64> {
     Ok(hash)
 }
 
+fn read_xattrs(path: &Path) -> Vec<(String, Vec<u8>)> {
+    let names = match xattr::list(path) {
+        Ok(names) => names,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut out: Vec<(String, Vec<u8>)> = Vec::new();
+    for name in names {
+        let value = xattr::get(path, &name).ok().flatten().unwrap_or_default();
+        out.push((name.to_string_lossy().into_owned(), value));
+    }
+    out.sort_by(|a, b| a.0.cmp(&b.0));
+    out
+}
+
+#[derive(bincode::Encode, bincode::Decode)]
+struct Meta {
+    mode: u32,
+    uid: u32,
+    gid: u32,
+    size: u64,
+    mtime: i64,
+    ctime: i64,
+    ino: u64,
+    nlink: u64,
+    policy: MetaPolicy,
+    xattrs: Vec<(String, Vec<u8>)>,
+}
+
+impl Meta {
+    fn from_path(path: &Path, policy: MetaPolicy, capture_xattrs: bool) -> std::io::Result<Self> {
+        let md = std::fs::symlink_metadata(path)?;
+
+        Ok(Self {
+            mode: md.mode(),
+            uid: md.uid(),
+            gid: md.gid(),
+            size: md.size(),
+            mtime: md.mtime(),
+            ctime: md.ctime(),
+            ino: md.ino(),
+            nlink: md.nlink(),
+            policy,
+            xattrs: if capture_xattrs {
+                read_xattrs(path)
+            } else {
+                Vec::new()
+            },
+        })
+    }
+
+    fn report_changes(&self, path: &Path, new: &Meta, sink: &Sink) {
+        let pathname = path.display();
+        if self.policy.mode && self.mode != new.mode {
+            sink.record(
+                path,
+                Change::MetadataChanged {
+                    field: "mode",
+                    old: format!("0o{:o}", self.mode),
+                    new: format!("0o{:o}", new.mode),
+                },
+            );
+            if !sink.is_json() {
+                eprintln!(
+                    "[{}] PERMISSIONS CHANGED (old: 0o{:o}|new: 0o{:o})",
+                    pathname, self.mode, new.mode
+                );
+            }
+        }
+        if self.policy.owner && (self.uid != new.uid || self.gid != new.gid) {
+            sink.record(
+                path,
+                Change::MetadataChanged {
+                    field: "owner",
+                    old: format!("{}:{}", self.uid, self.gid),
+                    new: format!("{}:{}", new.uid, new.gid),
+                },
+            );
+            if !sink.is_json() {
+                eprintln!(
+                    "[{}] OWNER CHANGED (old: {}:{}|new: {}:{})",
+                    pathname, self.uid, self.gid, new.uid, new.gid
+                );
+            }
+        }
+        if self.policy.size && self.size != new.size {
+            sink.record(
+                path,
+                Change::MetadataChanged {
+                    field: "size",
+                    old: self.size.to_string(),
+                    new: new.size.to_string(),
+                },
+            );
+            if !sink.is_json() {
+                eprintln!(
+                    "[{}] SIZE CHANGED (old: {}|new: {})",
+                    pathname, self.size, new.size
+                );
+            }
+        }
+        if self.policy.mtime && self.mtime != new.mtime {
+            sink.record(
+                path,
+                Change::MetadataChanged {
+                    field: "mtime",
+                    old: self.mtime.to_string(),
+                    new: new.mtime.to_string(),
+                },
+            );
+            if !sink.is_json() {
+                eprintln!(
+                    "[{}] MTIME CHANGED (old: {}|new: {})",
+                    pathname, self.mtime, new.mtime
+                );
+            }
+        }
+        if self.policy.ctime && self.ctime != new.ctime {
+            sink.record(
+                path,
+                Change::MetadataChanged {
+                    field: "ctime",
+                    old: self.ctime.to_string(),
+                    new: new.ctime.to_string(),
+                },
+            );
+            if !sink.is_json() {
+                eprintln!(
+                    "[{}] CTIME CHANGED (old: {}|new: {})",
+                    pathname, self.ctime, new.ctime
+                );
+            }
+        }
+        if self.policy.inode && self.ino != new.ino {
+            sink.record(
+                path,
+                Change::MetadataChanged {
+                    field: "inode",
+                    old: self.ino.to_string(),
+                    new: new.ino.to_string(),
+                },
+            );
+            if !sink.is_json() {
+                eprintln!(
+                    "[{}] INODE CHANGED (old: {}|new: {})",
+                    pathname, self.ino, new.ino
+                );
+            }
+        }
+        if self.policy.nlink && self.nlink != new.nlink {
+            sink.record(
+                path,
+                Change::MetadataChanged {
+                    field: "nlink",
+                    old: self.nlink.to_string(),
+                    new: new.nlink.to_string(),
+                },
+            );
+            if !sink.is_json() {
+                eprintln!(
+                    "[{}] LINK COUNT CHANGED (old: {}|new: {})",
+                    pathname, self.nlink, new.nlink
+                );
+            }
+        }
+
+        self.report_xattr_changes(path, new, sink);
+    }
+
+    fn report_xattr_changes(&self, path: &Path, new: &Meta, sink: &Sink) {
+        if self.xattrs.is_empty() && new.xattrs.is_empty() {
+            return;
+        }
+
+        let pathname = path.display();
+        let (old, new) = (&self.xattrs, &new.xattrs);
+        let (mut i, mut j) = (0, 0);
+
+        while i < old.len() || j < new.len() {
+            match (old.get(i), new.get(j)) {
+                (Some((on, ov)), Some((nn, nv))) => {
+                    if on == nn {
+                        if ov != nv {
+                            sink.record(path, Change::XattrChanged { name: on.clone() });
+                            if !sink.is_json() {
+                                eprintln!("[{}] XATTR CHANGED ({})", pathname, on);
+                            }
+                        }
+                        i += 1;
+                        j += 1;
+                    } else if on < nn {
+                        sink.record(path, Change::XattrRemoved { name: on.clone() });
+                        if !sink.is_json() {
+                            eprintln!("[{}] XATTR REMOVED ({})", pathname, on);
+                        }
+                        i += 1;
+                    } else {
+                        sink.record(path, Change::XattrAdded { name: nn.clone() });
+                        if !sink.is_json() {
+                            eprintln!("[{}] XATTR ADDED ({})", pathname, nn);
+                        }
+                        j += 1;
+                    }
+                }
+                (Some((on, _)), None) => {
+                    sink.record(path, Change::XattrRemoved { name: on.clone() });
+                    if !sink.is_json() {
+                        eprintln!("[{}] XATTR REMOVED ({})", pathname, on);
+                    }
+                    i += 1;
+                }
+                (None, Some((nn, _))) => {
+                    sink.record(path, Change::XattrAdded { name: nn.clone() });
+                    if !sink.is_json() {
+                        eprintln!("[{}] XATTR ADDED ({})", pathname, nn);
+                    }
+                    j += 1;
+                }
+                (None, None) => break,
+            }
+        }
+    }
+}
+
+#[inline]
+fn major(rdev: u64) -> u64 {
+    ((rdev >> 8) & 0xfff) | ((rdev >> 32) & !0xfff)
+}
+
+#[inline]
+fn minor(rdev: u64) -> u64 {
+    (rdev & 0xff) | ((rdev >> 12) & !0xff)
+}
+
+#[inline]
+fn report_device_change(path: &Path, old_rdev: u64, new_rdev: u64, sink: &Sink) {
+    if old_rdev != new_rdev {
+        sink.record(
+            path,
+            Change::DeviceNumbersChanged {
+                old: format!("{}:{}", major(old_rdev), minor(old_rdev)),
+                new: format!("{}:{}", major(new_rdev), minor(new_rdev)),
+            },
+        );
+        if !sink.is_json() {
+            eprintln!(
+                "[{}] DEVICE NUMBERS CHANGED (old: {}:{}|new: {}:{})",
+                path.display(),
+                major(old_rdev),
+                minor(old_rdev),
+                major(new_rdev),
+                minor(new_rdev)
+            );
+        }
+    }
+}
+
 #[derive(bincode::Encode, bincode::Decode)]
 enum NodeType {
-    F(u64),
-    D,
-    L,
+    F(u64, Meta),
+    D(Meta),
+    L(Meta),
+    B(u64, Meta),
+    C(u64, Meta),
+    P(Meta),
+    S(Meta),
+}
+
+impl NodeType {
+    fn kind_name(&self) -> &'static str {
+        match self {
+            NodeType::F(..) => "A REGULAR FILE",
+            NodeType::D(_) => "A DIRECTORY",
+            NodeType::L(_) => "A SYMLINK",
+            NodeType::B(..) => "A BLOCK DEVICE",
+            NodeType::C(..) => "A CHARACTER DEVICE",
+            NodeType::P(_) => "A FIFO",
+            NodeType::S(_) => "A SOCKET",
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            NodeType::F(..) => "file",
+            NodeType::D(_) => "directory",
+            NodeType::L(_) => "symlink",
+            NodeType::B(..) => "block_device",
+            NodeType::C(..) => "char_device",
+            NodeType::P(_) => "fifo",
+            NodeType::S(_) => "socket",
+        }
+    }
 }
 
+const CHANNEL_SLOTS_PER_WORKER: usize = 2;
+
 #[inline]
 fn scan_path(
     config: &Config,
-    root_path: &str,
+    entry: &ScanEntry,
     db: &mut HashMap<PathBuf, NodeType>,
+    jobs: usize,
 ) -> std::io::Result<()> {
-    let mut hasher = Xxh3::with_secret(config.secret.clone());
+    let policy = entry.policy;
+    let capture_xattrs = config.check_xattrs;
+    let jobs = jobs.max(1);
+
+    // File hashing runs on a pool of workers fed by a bounded channel, while
+    // directory discovery stays on this thread to keep the ignore checks and
+    // the `db` owned by a single thread. Hashed files come back over `res_rx`.
+    let (work_tx, work_rx) = mpsc::sync_channel::<PathBuf>(jobs * CHANNEL_SLOTS_PER_WORKER);
+    let work_rx = Arc::new(Mutex::new(work_rx));
+    let (res_tx, res_rx) = mpsc::channel::<(PathBuf, NodeType)>();
+
+    let mut workers = Vec::with_capacity(jobs);
+    for _ in 0..jobs {
+        let work_rx: Arc<Mutex<Receiver<PathBuf>>> = Arc::clone(&work_rx);
+        let res_tx = res_tx.clone();
+        let secret = config.secret;
+        workers.push(thread::spawn(move || {
+            let mut hasher = Xxh3::with_secret(secret);
+            loop {
+                let path = match work_rx.lock().unwrap().recv() {
+                    Ok(path) => path,
+                    Err(_) => break,
+                };
+                match get_filehash(&mut hasher, &path) {
+                    Ok(hash) => match Meta::from_path(&path, policy, capture_xattrs) {
+                        Ok(meta) => {
+                            let _ = res_tx.send((path, NodeType::F(hash, meta)));
+                        }
+                        Err(error) => {
+                            eprintln!("Exception on: \"{}\" [{}]", path.display(), error)
+                        }
+                    },
+                    Err(error) => eprintln!("Exception on: \"{}\" [{}]", path.display(), error),
+                }
+            }
+        }));
+    }
+    drop(res_tx);
 
     let mut pathstack: Vec<PathBuf> = Vec::new();
-    pathstack.push(PathBuf::from(root_path));
+    pathstack.push(PathBuf::from(&entry.path));
 
     while let Some(path) = pathstack.pop() {
         let pathname = path.display();
@@ -56,19 +388,41 @@ fn scan_path(
 
         if path.is_symlink() {
             println!("[symlink] {}", pathname);
-            db.insert(path, NodeType::L);
+            match Meta::from_path(&path, policy, capture_xattrs) {
+                Ok(meta) => {
+                    db.insert(path, NodeType::L(meta));
+                }
+                Err(error) => eprintln!("Exception on: \"{}\" [{}]", pathname, error),
+            }
             continue;
         }
 
         if path.is_file() {
             println!("[file] {}", pathname);
-            match get_filehash(&mut hasher, &path) {
-                Ok(hash) => {
-                    db.insert(path, NodeType::F(hash));
-                }
-                Err(error) => {
-                    eprintln!("Exception on: \"{}\" [{}]", pathname, error);
+            work_tx.send(path).unwrap();
+            continue;
+        }
+
+        let md = std::fs::symlink_metadata(&path)?;
+        let ft = md.file_type();
+        if ft.is_block_device() || ft.is_char_device() || ft.is_fifo() || ft.is_socket() {
+            match Meta::from_path(&path, policy, capture_xattrs) {
+                Ok(meta) => {
+                    if ft.is_block_device() {
+                        println!("[block] {}", pathname);
+                        db.insert(path, NodeType::B(md.rdev(), meta));
+                    } else if ft.is_char_device() {
+                        println!("[char] {}", pathname);
+                        db.insert(path, NodeType::C(md.rdev(), meta));
+                    } else if ft.is_fifo() {
+                        println!("[fifo] {}", pathname);
+                        db.insert(path, NodeType::P(meta));
+                    } else {
+                        println!("[socket] {}", pathname);
+                        db.insert(path, NodeType::S(meta));
+                    }
                 }
+                Err(error) => eprintln!("Exception on: \"{}\" [{}]", pathname, error),
             }
             continue;
         }
@@ -77,32 +431,47 @@ fn scan_path(
             Ok(e) => e,
             Err(_) => continue,
         };
-        let mut n_elems = 0;
+
+        println!("[dir] {}", pathname);
+        match Meta::from_path(&path, policy, capture_xattrs) {
+            Ok(meta) => {
+                db.insert(path, NodeType::D(meta));
+            }
+            Err(error) => eprintln!("Exception on: \"{}\" [{}]", pathname, error),
+        }
 
         for i in it {
-            n_elems += 1;
             if let Ok(e) = i {
                 pathstack.push(e.path());
             }
         }
+    }
 
-        if n_elems == 0 {
-            println!("[dir] {}", pathname);
-            db.insert(path, NodeType::D);
-        }
+    drop(work_tx);
+    for worker in workers {
+        let _ = worker.join();
+    }
+    for (path, node) in res_rx {
+        db.insert(path, node);
     }
 
     Ok(())
 }
 
-pub fn gen_db(config: &Config, outfile: &str, passphrase: &str) -> Result<(), CryptoError> {
+pub fn gen_db(
+    config: &Config,
+    outfile: &str,
+    passphrase: &str,
+    enc: EncryptionType,
+    jobs: usize,
+) -> Result<(), CryptoError> {
     let mut db: HashMap<PathBuf, NodeType> = HashMap::new();
 
-    for root_path in &config.scans {
-        scan_path(config, root_path, &mut db)?;
+    for entry in &config.scans {
+        scan_path(config, entry, &mut db, jobs)?;
     }
 
-    save_encrypted(db, outfile, passphrase)?;
+    save_encrypted(db, outfile, passphrase, enc)?;
 
     Ok(())
 }
@@ -110,104 +479,289 @@ pub fn gen_db(config: &Config, outfile: &str, passphrase: &str) -> Result<(), Cr
 #[inline]
 fn compare_path(
     config: &Config,
-    root_path: &str,
+    entry: &ScanEntry,
     db: &mut HashMap<PathBuf, NodeType>,
+    jobs: usize,
+    sink: &Sink,
 ) -> std::io::Result<()> {
-    let mut hasher = Xxh3::with_secret(config.secret.clone());
+    let policy = entry.policy;
+    let capture_xattrs = config.check_xattrs;
+    let jobs = jobs.max(1);
+
+    // As in `scan_path`, hashing is funnelled to a worker pool. The walk thread
+    // owns `db`, removing each visited entry and handing only regular-file
+    // comparisons (the ones that need hashing) to the workers.
+    let (work_tx, work_rx): (SyncSender<(PathBuf, u64, Meta)>, _) =
+        mpsc::sync_channel(jobs * CHANNEL_SLOTS_PER_WORKER);
+    let work_rx = Arc::new(Mutex::new(work_rx));
+
+    let mut workers = Vec::with_capacity(jobs);
+    for _ in 0..jobs {
+        let work_rx: Arc<Mutex<Receiver<(PathBuf, u64, Meta)>>> = Arc::clone(&work_rx);
+        let secret = config.secret;
+        let sink = sink.clone();
+        workers.push(thread::spawn(move || {
+            let mut hasher = Xxh3::with_secret(secret);
+            loop {
+                let (path, old_hash, old_meta) = match work_rx.lock().unwrap().recv() {
+                    Ok(work) => work,
+                    Err(_) => break,
+                };
+                let pathname = path.display();
+                match get_filehash(&mut hasher, &path) {
+                    Ok(new_hash) => {
+                        if old_hash != new_hash {
+                            sink.record(
+                                &path,
+                                Change::HashChanged {
+                                    old: format!("0x{:016x}", old_hash),
+                                    new: format!("0x{:016x}", new_hash),
+                                },
+                            );
+                            if !sink.is_json() {
+                                eprintln!(
+                                    "[{}] HASH CHANGED (old: 0x{:016x}|new: 0x{:016x})",
+                                    pathname, old_hash, new_hash
+                                );
+                            }
+                        }
+                        match Meta::from_path(&path, policy, capture_xattrs) {
+                            Ok(new_meta) => old_meta.report_changes(&path, &new_meta, &sink),
+                            Err(error) => {
+                                sink.record(&path, Change::VerifyFailed { error: error.to_string() });
+                                eprintln!("Exception on: \"{}\" [{}]", pathname, error)
+                            }
+                        }
+                    }
+                    Err(error) => {
+                        sink.record(&path, Change::VerifyFailed { error: error.to_string() });
+                        eprintln!("Exception on: \"{}\" [{}]", pathname, error)
+                    }
+                }
+            }
+        }));
+    }
 
     let mut pathstack: Vec<PathBuf> = Vec::new();
-    pathstack.push(PathBuf::from(root_path));
+    pathstack.push(PathBuf::from(&entry.path));
 
     while let Some(path) = pathstack.pop() {
         let pathname = path.display();
 
         if config.ignores.contains(path.as_path()) {
-            println!("Skipping \"{}\"", pathname);
+            eprintln!("Skipping \"{}\"", pathname);
             continue;
         }
 
         if path.is_symlink() {
             match db.remove(path.as_path()) {
-                Some(NodeType::F(hash)) => {
-                    eprintln!(
-                        "[{}] SYMLINK WAS PREVIOUSLY A FILE (0x{:016x})",
-                        pathname, hash
-                    )
+                Some(NodeType::L(old_meta)) => match Meta::from_path(&path, policy, capture_xattrs) {
+                    Ok(new_meta) => old_meta.report_changes(&path, &new_meta, sink),
+                    Err(error) => {
+                        sink.record(&path, Change::VerifyFailed { error: error.to_string() });
+                        eprintln!("Exception on: \"{}\" [{}]", pathname, error);
+                    }
+                },
+                Some(other) => {
+                    sink.record(&path, Change::TypeChanged { from: other.type_name(), to: "symlink" });
+                    if !sink.is_json() {
+                        eprintln!("[{}] WAS {}, NOW A SYMLINK", pathname, other.kind_name());
+                    }
                 }
-                Some(NodeType::D) => {
-                    eprintln!("[{}] SYMLINK WAS PREVIOUSLY A DIRECTORY", pathname);
+                None => {
+                    sink.record(&path, Change::NewSymlink);
+                    if !sink.is_json() {
+                        eprintln!("[{}] NEW SYMLINK", pathname);
+                    }
                 }
-                Some(NodeType::L) => (),
-                None => eprintln!("[{}] NEW SYMLINK", pathname),
             }
             continue;
         }
 
         if path.is_file() {
             match db.remove(path.as_path()) {
-                Some(NodeType::F(old_hash)) => {
-                    let new_hash = get_filehash(&mut hasher, &path)?;
-                    if old_hash != new_hash {
-                        eprintln!(
-                            "[{}] HASH CHANGED (old: 0x{:016x}|new: 0x{:016x})",
-                            pathname, old_hash, new_hash
-                        )
-                    }
+                Some(NodeType::F(old_hash, old_meta)) => {
+                    work_tx.send((path, old_hash, old_meta)).unwrap();
                 }
-                Some(NodeType::D) => {
-                    eprintln!("[{}] FILE WAS PREVIOUSLY A DIRECTORY", pathname);
+                Some(other) => {
+                    sink.record(&path, Change::TypeChanged { from: other.type_name(), to: "file" });
+                    if !sink.is_json() {
+                        eprintln!("[{}] WAS {}, NOW A REGULAR FILE", pathname, other.kind_name());
+                    }
                 }
-                Some(NodeType::L) => {
-                    eprintln!("[{}] FILE WAS PREVIOUSLY A SYMLINK", pathname);
+                None => {
+                    sink.record(&path, Change::NewFile);
+                    if !sink.is_json() {
+                        eprintln!("[{}] NEW FILE", pathname);
+                    }
                 }
-                None => eprintln!("[{}] NEW FILE", pathname),
             }
             continue;
         }
 
-        let it = match path.read_dir() {
-            Ok(e) => e,
-            Err(_) => continue,
-        };
-        let mut n_elems = 0;
+        let md = std::fs::symlink_metadata(&path)?;
+        let ft = md.file_type();
+        if ft.is_block_device() || ft.is_char_device() || ft.is_fifo() || ft.is_socket() {
+            let now_type = if ft.is_block_device() {
+                "block_device"
+            } else if ft.is_char_device() {
+                "char_device"
+            } else if ft.is_fifo() {
+                "fifo"
+            } else {
+                "socket"
+            };
+            match db.remove(path.as_path()) {
+                Some(NodeType::B(old_rdev, old_meta)) if ft.is_block_device() => {
+                    match Meta::from_path(&path, policy, capture_xattrs) {
+                        Ok(new_meta) => {
+                            report_device_change(&path, old_rdev, md.rdev(), sink);
+                            old_meta.report_changes(&path, &new_meta, sink);
+                        }
+                        Err(error) => {
+                            sink.record(&path, Change::VerifyFailed { error: error.to_string() });
+                            eprintln!("Exception on: \"{}\" [{}]", pathname, error);
+                        }
+                    }
+                }
+                Some(NodeType::C(old_rdev, old_meta)) if ft.is_char_device() => {
+                    match Meta::from_path(&path, policy, capture_xattrs) {
+                        Ok(new_meta) => {
+                            report_device_change(&path, old_rdev, md.rdev(), sink);
+                            old_meta.report_changes(&path, &new_meta, sink);
+                        }
+                        Err(error) => {
+                            sink.record(&path, Change::VerifyFailed { error: error.to_string() });
+                            eprintln!("Exception on: \"{}\" [{}]", pathname, error);
+                        }
+                    }
+                }
+                Some(NodeType::P(old_meta)) if ft.is_fifo() => {
+                    match Meta::from_path(&path, policy, capture_xattrs) {
+                        Ok(new_meta) => old_meta.report_changes(&path, &new_meta, sink),
+                        Err(error) => {
+                            sink.record(&path, Change::VerifyFailed { error: error.to_string() });
+                            eprintln!("Exception on: \"{}\" [{}]", pathname, error);
+                        }
+                    }
+                }
+                Some(NodeType::S(old_meta)) if ft.is_socket() => {
+                    match Meta::from_path(&path, policy, capture_xattrs) {
+                        Ok(new_meta) => old_meta.report_changes(&path, &new_meta, sink),
+                        Err(error) => {
+                            sink.record(&path, Change::VerifyFailed { error: error.to_string() });
+                            eprintln!("Exception on: \"{}\" [{}]", pathname, error);
+                        }
+                    }
+                }
+                Some(other) => {
+                    sink.record(&path, Change::TypeChanged { from: other.type_name(), to: now_type });
+                    if !sink.is_json() {
+                        let now = if ft.is_block_device() {
+                            "A BLOCK DEVICE"
+                        } else if ft.is_char_device() {
+                            "A CHARACTER DEVICE"
+                        } else if ft.is_fifo() {
+                            "A FIFO"
+                        } else {
+                            "A SOCKET"
+                        };
+                        eprintln!("[{}] WAS {}, NOW {}", pathname, other.kind_name(), now);
+                    }
+                }
+                None => {
+                    sink.record(&path, Change::NewDevice { kind: now_type });
+                    if !sink.is_json() {
+                        let now = if ft.is_block_device() {
+                            "BLOCK DEVICE"
+                        } else if ft.is_char_device() {
+                            "CHARACTER DEVICE"
+                        } else if ft.is_fifo() {
+                            "FIFO"
+                        } else {
+                            "SOCKET"
+                        };
+                        eprintln!("[{}] NEW {}", pathname, now);
+                    }
+                }
+            }
+            continue;
+        }
 
-        for i in it {
-            n_elems += 1;
-            if let Ok(e) = i {
-                pathstack.push(e.path());
+        match db.remove(path.as_path()) {
+            Some(NodeType::D(old_meta)) => match Meta::from_path(&path, policy, capture_xattrs) {
+                Ok(new_meta) => old_meta.report_changes(&path, &new_meta, sink),
+                Err(error) => {
+                    sink.record(&path, Change::VerifyFailed { error: error.to_string() });
+                    eprintln!("Exception on: \"{}\" [{}]", pathname, error);
+                }
+            },
+            Some(other) => {
+                sink.record(&path, Change::TypeChanged { from: other.type_name(), to: "directory" });
+                if !sink.is_json() {
+                    eprintln!("[{}] WAS {}, NOW A DIRECTORY", pathname, other.kind_name());
+                }
+            }
+            None => {
+                sink.record(&path, Change::NewDirectory);
+                if !sink.is_json() {
+                    eprintln!("[{}] NEW DIRECTORY", pathname);
+                }
             }
         }
 
-        if n_elems == 0 {
-            match db.remove(path.as_path()) {
-                Some(NodeType::F(_)) => eprintln!("[{}] FILE IS NOW A DIRECTORY", pathname),
-                Some(NodeType::L) => eprintln!("[{}] SYMLINK IS NOW A DIRECTORY", pathname),
-                Some(NodeType::D) => (),
-                None => eprintln!("[{}] NEW DIRECTORY", pathname),
+        // Descending requires listing the directory, but a chmod-000 directory
+        // still diffs fine above via `symlink_metadata` — only skip its children.
+        if let Ok(it) = path.read_dir() {
+            for i in it {
+                if let Ok(e) = i {
+                    pathstack.push(e.path());
+                }
             }
         }
     }
 
+    drop(work_tx);
+    for worker in workers {
+        let _ = worker.join();
+    }
+
     Ok(())
 }
 
-pub fn compare_db(config: &Config, dbfile: &str, passphrase: &str) -> Result<(), CryptoError> {
+pub fn compare_db(
+    config: &Config,
+    dbfile: &str,
+    passphrase: &str,
+    jobs: usize,
+    json: bool,
+) -> Result<bool, CryptoError> {
     let mut db: HashMap<PathBuf, NodeType> = read_decrypted(dbfile, passphrase)?;
+    let sink = if json { Sink::json() } else { Sink::text() };
 
-    for root_path in &config.scans {
-        compare_path(config, root_path, &mut db)?;
+    for entry in &config.scans {
+        compare_path(config, entry, &mut db, jobs, &sink)?;
     }
 
     for (k, v) in db.iter() {
-        let k = k.display();
-        match v {
-            NodeType::F(hash) => eprintln!("[{k}] FILE WITH HASH 0x{hash:016x} IS REMOVED"),
-            NodeType::D => eprintln!("[{k}] DIRECTORY IS REMOVED"),
-            NodeType::L => eprintln!("[{k}] SYMLINK IS REMOVED"),
+        sink.record(k, Change::Removed { kind: v.type_name() });
+        if !sink.is_json() {
+            let kd = k.display();
+            match v {
+                NodeType::F(hash, _) => eprintln!("[{kd}] FILE WITH HASH 0x{hash:016x} IS REMOVED"),
+                NodeType::B(rdev, _) => {
+                    eprintln!("[{kd}] BLOCK DEVICE {}:{} IS REMOVED", major(*rdev), minor(*rdev))
+                }
+                NodeType::C(rdev, _) => {
+                    eprintln!("[{kd}] CHARACTER DEVICE {}:{} IS REMOVED", major(*rdev), minor(*rdev))
+                }
+                other => eprintln!("[{kd}] {} IS REMOVED", other.kind_name()),
+            }
         }
     }
 
-    Ok(())
+    let roots: Vec<String> = config.scans.iter().map(|e| e.path.clone()).collect();
+    Ok(sink.finish(roots))
 }
 
 pub fn print_db(dbfile: &str, passphrase: &str) -> Result<(), CryptoError> {